@@ -0,0 +1,213 @@
+use crate::parse_header_field;
+use http::header::HeaderMap;
+use std::io;
+use std::io::{BufRead, Read};
+
+// Chunk sizes above this are rejected outright rather than allocated for,
+// since a chunk size is attacker-controlled before we've validated anything
+// else about the request.
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+const TRAILER_LINE_CAP: usize = 16384;
+
+// Bounds a chunk-size line (size plus any chunk-extension) the same way
+// `TRAILER_LINE_CAP` bounds a trailer line, so a peer can't grow `line`
+// unboundedly before `MAX_CHUNK_SIZE` ever gets a chance to reject it.
+const CHUNK_SIZE_LINE_CAP: usize = 16384;
+
+#[derive(Debug)]
+pub enum InvalidChunkedBody {
+    ChunkSize,
+    ChunkTooLarge,
+    MissingChunkTerminator,
+    Trailer,
+    Truncated,
+}
+
+fn decode_err(e: InvalidChunkedBody) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e))
+}
+
+enum State {
+    ChunkSize,
+    ChunkData(usize),
+    ChunkTerminator,
+    Done,
+}
+
+// Decodes a `Transfer-Encoding: chunked` body read from `inner` into a plain
+// byte stream. Trailer fields (if any) are available via `trailers()` once
+// `read` has returned `Ok(0)`.
+pub struct ChunkedReader<B> {
+    inner: B,
+    state: State,
+    trailers: HeaderMap,
+}
+
+impl<B: BufRead> ChunkedReader<B> {
+    pub fn new(inner: B) -> Self {
+        ChunkedReader {
+            inner,
+            state: State::ChunkSize,
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    pub fn trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    fn read_chunk_size(&mut self) -> io::Result<usize> {
+        let mut line = Vec::with_capacity(CHUNK_SIZE_LINE_CAP);
+        let count = self.inner
+            .by_ref()
+            .take(CHUNK_SIZE_LINE_CAP as u64)
+            .read_until(b'\n', &mut line)?;
+        if count == 0 || !line.ends_with(b"\r\n") {
+            return Err(decode_err(InvalidChunkedBody::ChunkSize));
+        }
+        line.truncate(line.len() - 2);
+        let size_str = match line.iter().position(|&b| b == b';') {
+            Some(i) => &line[..i],
+            None => &line[..],
+        };
+        let size_str = std::str::from_utf8(size_str)
+            .map_err(|_| decode_err(InvalidChunkedBody::ChunkSize))?;
+        let size = usize::from_str_radix(size_str, 16)
+            .map_err(|_| decode_err(InvalidChunkedBody::ChunkSize))?;
+        if size > MAX_CHUNK_SIZE {
+            return Err(decode_err(InvalidChunkedBody::ChunkTooLarge));
+        }
+        Ok(size)
+    }
+
+    fn read_trailers(&mut self) -> io::Result<()> {
+        let mut line = Vec::with_capacity(TRAILER_LINE_CAP);
+        loop {
+            line.clear();
+            let count = self.inner
+                .by_ref()
+                .take(TRAILER_LINE_CAP as u64)
+                .read_until(b'\n', &mut line)?;
+            if count == 0 || !line.ends_with(b"\r\n") {
+                return Err(decode_err(InvalidChunkedBody::Trailer));
+            }
+            line.truncate(line.len() - 2);
+            if line == b"" {
+                return Ok(());
+            }
+            let (name, value) = parse_header_field(&line)
+                .map_err(|_| decode_err(InvalidChunkedBody::Trailer))?;
+            self.trailers.insert(name, value);
+        }
+    }
+}
+
+impl<B: BufRead> Read for ChunkedReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::ChunkSize => {
+                    let size = self.read_chunk_size()?;
+                    self.state = if size == 0 {
+                        self.read_trailers()?;
+                        State::Done
+                    } else {
+                        State::ChunkData(size)
+                    };
+                }
+                State::ChunkData(0) => {
+                    self.state = State::ChunkTerminator;
+                }
+                State::ChunkData(remaining) => {
+                    let to_read = remaining.min(buf.len());
+                    if to_read == 0 {
+                        return Ok(0);
+                    }
+                    let n = self.inner.read(&mut buf[..to_read])?;
+                    if n == 0 {
+                        return Err(decode_err(InvalidChunkedBody::Truncated));
+                    }
+                    self.state = State::ChunkData(remaining - n);
+                    return Ok(n);
+                }
+                State::ChunkTerminator => {
+                    let mut term = [0u8; 2];
+                    self.inner.read_exact(&mut term)?;
+                    if &term != b"\r\n" {
+                        return Err(decode_err(InvalidChunkedBody::MissingChunkTerminator));
+                    }
+                    self.state = State::ChunkSize;
+                }
+            }
+        }
+    }
+}
+
+// Reads exactly `length` bytes from `inner`, then reports EOF; for
+// `Content-Length` bodies, which need no decoding, just a boundary.
+pub struct LengthReader<B> {
+    inner: B,
+    remaining: u64,
+}
+
+impl<B: Read> LengthReader<B> {
+    pub fn new(inner: B, length: u64) -> Self {
+        LengthReader { inner, remaining: length }
+    }
+}
+
+impl<B: Read> Read for LengthReader<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let cap = self.remaining.min(buf.len() as u64) as usize;
+        let n = self.inner.read(&mut buf[..cap])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::body::{ChunkedReader, LengthReader};
+    use std::io::Read;
+
+    #[test]
+    fn test_chunked_reader() {
+        let s = b"4\r\nWiki\r\n5\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\n\r\n";
+        let mut r = ChunkedReader::new(&s[..]);
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(&out[..], &b"Wikipedia in\r\n\r\nchunks."[..]);
+    }
+
+    #[test]
+    fn test_chunked_reader_extension_and_trailer() {
+        let s = b"3;ignored-ext=1\r\nfoo\r\n0\r\nX-Trailer: hi\r\n\r\n";
+        let mut r = ChunkedReader::new(&s[..]);
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(&out[..], &b"foo"[..]);
+        assert_eq!(r.trailers()["x-trailer"], "hi");
+    }
+
+    #[test]
+    fn test_chunked_reader_rejects_truncated_chunk() {
+        let s = b"4\r\nWik";
+        let mut r = ChunkedReader::new(&s[..]);
+        let mut out = Vec::new();
+        assert!(r.read_to_end(&mut out).is_err());
+    }
+
+    #[test]
+    fn test_length_reader() {
+        let s = b"hello, world, and then some extra";
+        let mut r = LengthReader::new(&s[..], 12);
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(&out[..], &b"hello, world"[..]);
+    }
+}
@@ -0,0 +1,185 @@
+use http::header::{HeaderMap, CONTENT_LENGTH, EXPECT, TRANSFER_ENCODING};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Coding {
+    Chunked,
+    Other(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Framing {
+    // No Transfer-Encoding or Content-Length; the body (if any) is
+    // delimited some other way, e.g. by the connection closing.
+    None,
+    Length(u64),
+    Chunked,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvalidFraming {
+    MalformedContentLength,
+    ConflictingContentLength,
+    // RFC 7230 section 3.3.3 step 3: a server MUST reject a message with
+    // both Transfer-Encoding and Content-Length.
+    BothTransferEncodingAndContentLength,
+    // RFC 7230 section 3.3.1: "chunked" MUST be the last coding.
+    ChunkedNotFinal,
+}
+
+pub fn content_length(fields: &HeaderMap) -> Result<Option<u64>, InvalidFraming> {
+    let mut result = None;
+    for value in fields.get_all(CONTENT_LENGTH).iter() {
+        let value = value.to_str()
+            .map_err(|_| InvalidFraming::MalformedContentLength)?;
+        // RFC 7230 section 3.3.2: Content-Length = 1*DIGIT, with no sign;
+        // `u64::from_str` is laxer than that (it accepts a leading "+"),
+        // and a parser that's laxer than the grammar here is a classic
+        // request-smuggling vector, so reject anything but plain digits
+        // up front.
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidFraming::MalformedContentLength);
+        }
+        let n: u64 = value.parse()
+            .map_err(|_| InvalidFraming::MalformedContentLength)?;
+        match result {
+            None => result = Some(n),
+            Some(prev) if prev == n => {}
+            Some(_) => return Err(InvalidFraming::ConflictingContentLength),
+        }
+    }
+    Ok(result)
+}
+
+pub fn transfer_encoding(fields: &HeaderMap) -> Vec<Coding> {
+    fields.get_all(TRANSFER_ENCODING).iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tok| tok.trim())
+        .filter(|tok| !tok.is_empty())
+        .map(|tok| {
+            if tok.eq_ignore_ascii_case("chunked") {
+                Coding::Chunked
+            } else {
+                Coding::Other(tok.to_lowercase())
+            }
+        })
+        .collect()
+}
+
+pub fn expects_continue(fields: &HeaderMap) -> bool {
+    fields.get_all(EXPECT).iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.eq_ignore_ascii_case("100-continue"))
+}
+
+// Encodes the RFC 7230 section 3.3.3 framing rules a server needs to know
+// how to read the body of a request (or response) whose headers have
+// already been validated individually.
+pub fn framing(fields: &HeaderMap) -> Result<Framing, InvalidFraming> {
+    let codings = transfer_encoding(fields);
+    let length = content_length(fields)?;
+
+    if !codings.is_empty() {
+        if length.is_some() {
+            return Err(InvalidFraming::BothTransferEncodingAndContentLength);
+        }
+        if codings.last() != Some(&Coding::Chunked) {
+            return Err(InvalidFraming::ChunkedNotFinal);
+        }
+        return Ok(Framing::Chunked);
+    }
+
+    Ok(match length {
+        Some(n) => Framing::Length(n),
+        None => Framing::None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::framing::{
+        content_length,
+        expects_continue,
+        framing,
+        transfer_encoding,
+        Coding,
+        Framing,
+        InvalidFraming,
+    };
+    use http::header::{HeaderMap, HeaderValue, CONTENT_LENGTH, EXPECT, TRANSFER_ENCODING};
+
+    fn fields(pairs: &[(http::header::HeaderName, &str)]) -> HeaderMap {
+        let mut fields = HeaderMap::new();
+        for (name, value) in pairs {
+            fields.append(name.clone(), HeaderValue::from_str(value).unwrap());
+        }
+        fields
+    }
+
+    #[test]
+    fn test_content_length() {
+        assert_eq!(content_length(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(
+            content_length(&fields(&[(CONTENT_LENGTH, "42")])).unwrap(),
+            Some(42),
+        );
+        assert_eq!(
+            content_length(&fields(&[(CONTENT_LENGTH, "not a number")])),
+            Err(InvalidFraming::MalformedContentLength),
+        );
+        assert_eq!(
+            content_length(&fields(&[
+                (CONTENT_LENGTH, "42"),
+                (CONTENT_LENGTH, "7"),
+            ])),
+            Err(InvalidFraming::ConflictingContentLength),
+        );
+        // Repeating the same value is fine (e.g. after a proxy coalesces).
+        assert_eq!(
+            content_length(&fields(&[
+                (CONTENT_LENGTH, "42"),
+                (CONTENT_LENGTH, "42"),
+            ])).unwrap(),
+            Some(42),
+        );
+    }
+
+    #[test]
+    fn test_transfer_encoding() {
+        assert_eq!(transfer_encoding(&HeaderMap::new()), vec![]);
+        assert_eq!(
+            transfer_encoding(&fields(&[(TRANSFER_ENCODING, "gzip, chunked")])),
+            vec![Coding::Other("gzip".to_string()), Coding::Chunked],
+        );
+    }
+
+    #[test]
+    fn test_expects_continue() {
+        assert!(!expects_continue(&HeaderMap::new()));
+        assert!(expects_continue(&fields(&[(EXPECT, "100-continue")])));
+    }
+
+    #[test]
+    fn test_framing() {
+        assert_eq!(framing(&HeaderMap::new()).unwrap(), Framing::None);
+        assert_eq!(
+            framing(&fields(&[(CONTENT_LENGTH, "10")])).unwrap(),
+            Framing::Length(10),
+        );
+        assert_eq!(
+            framing(&fields(&[(TRANSFER_ENCODING, "chunked")])).unwrap(),
+            Framing::Chunked,
+        );
+        assert_eq!(
+            framing(&fields(&[
+                (TRANSFER_ENCODING, "chunked"),
+                (CONTENT_LENGTH, "10"),
+            ])),
+            Err(InvalidFraming::BothTransferEncodingAndContentLength),
+        );
+        assert_eq!(
+            framing(&fields(&[(TRANSFER_ENCODING, "chunked, gzip")])),
+            Err(InvalidFraming::ChunkedNotFinal),
+        );
+    }
+}
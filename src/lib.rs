@@ -10,13 +10,14 @@ use http::header::{
     HeaderValue,
     InvalidHeaderName,
     InvalidHeaderValue,
+    HOST,
 };
 use http::method::InvalidMethod;
 use http::uri::InvalidUriBytes;
 use lazy_static::lazy_static;
 use regex::bytes::Regex;
 use std::io;
-use std::io::{BufRead, BufWriter, Read, Write};
+use std::io::{BufRead, BufWriter, Write};
 
 #[derive(Debug)]
 pub struct RequestHeader {
@@ -35,7 +36,15 @@ pub struct ResponseHeader {
 
 #[derive(Debug)]
 pub enum InvalidRequestHeader {
-    Format,
+    // The peer closed the connection before a complete header arrived. A
+    // non-blocking caller should read more and try again; this is only
+    // final once the peer has actually gone away. An over-long line is
+    // reported as `Malformed` instead, since no amount of further reading
+    // can make it valid.
+    Truncated,
+    // The bytes received so far can never form a valid header, regardless
+    // of how much more data arrives.
+    Malformed,
     RequestLine(InvalidRequestLine),
     HeaderField(InvalidHeaderField),
     Io(io::Error),
@@ -59,45 +68,214 @@ impl From<io::Error> for InvalidRequestHeader {
     }
 }
 
+pub mod body;
+pub mod framing;
+pub mod media_type;
+
 const LINE_CAP: usize = 16384;
 
-pub fn parse_request_header<B: BufRead>(mut stream: B)
-    -> Result<RequestHeader, InvalidRequestHeader>
-{
-    // TODO: Why does removing the type from `line` here cause errors?
-    let next_line = |stream: &mut B, line: &mut Vec<u8>| {
-        line.clear();
-        let count = stream
-            .take(LINE_CAP as u64)
-            .read_until('\n' as u8, line)?;
-        match count {
-            0 => Err(InvalidRequestHeader::Format), // FIXME?
-            LINE_CAP => Err(InvalidRequestHeader::Format), // FIXME
-            _ => Ok(()),
+// RFC 7230 section 3.2.6: token = 1*tchar
+const TOKEN: &str = r"[!#$%&'*+.^_`|~0-9A-Za-z-]+";
+
+// RFC 7230 section 3.2.6: quoted-string = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+const QUOTED_STRING_1G: &str =
+    r#""(?:[\t !#-\x5B\x5D-~\x80-\xFF]|\\[\t !-~\x80-\xFF])*""#;
+
+// Result of feeding another chunk of bytes to a `RequestHeadParser`.
+#[derive(Debug)]
+pub enum Parse {
+    // `bytes_consumed` counts only the bytes of the buffer passed to this
+    // call of `feed` that belong to the header; anything after that is
+    // whatever came next on the wire (e.g. the start of the body) and is
+    // left for the caller to deal with.
+    Complete { header: Box<RequestHeader>, bytes_consumed: usize },
+    Partial,
+}
+
+// rfc7230 section 3.2.4: if obs-fold is used outside a message/http body,
+// a server MUST either reject the message or replace each obs-fold with
+// one or more SP characters. `Unfold` is the default since real-world
+// agents still emit folded headers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ObsFoldPolicy {
+    #[default]
+    Unfold,
+    Reject,
+}
+
+pub(crate) fn trim_ows(s: &[u8]) -> &[u8] {
+    let is_ows = |&b: &u8| b == b'\t' || b == b' ';
+    let start = s.iter().position(|b| !is_ows(b)).unwrap_or(s.len());
+    let end = s.iter().rposition(|b| !is_ows(b)).map(|i| i + 1).unwrap_or(0);
+    &s[start..end.max(start)]
+}
+
+// Incremental version of `parse_request_header` for non-blocking callers:
+// instead of blocking on a `BufRead`, feed it whatever bytes are available
+// right now and it reports whether that was enough.
+#[derive(Debug)]
+pub struct RequestHeadParser {
+    pending: Vec<u8>,
+    request_line: Option<(Method, Uri, Version)>,
+    // Name and not-yet-validated value of the field whose line(s) we've
+    // seen so far but haven't committed to `fields` yet, because obs-fold
+    // continuation lines might still extend its value.
+    field: Option<(HeaderName, Vec<u8>)>,
+    fields: HeaderMap,
+    obs_fold: ObsFoldPolicy,
+}
+
+impl Default for RequestHeadParser {
+    fn default() -> Self {
+        RequestHeadParser {
+            pending: Vec::new(),
+            request_line: None,
+            field: None,
+            fields: HeaderMap::new(),
+            obs_fold: ObsFoldPolicy::default(),
         }
-    };
+    }
+}
 
-    let mut line = Vec::with_capacity(LINE_CAP);
+impl RequestHeadParser {
+    pub fn new() -> Self {
+        RequestHeadParser::default()
+    }
 
-    next_line(&mut stream, &mut line)?;
-    if !line.ends_with(b"\r\n") {
-        return Err(InvalidRequestHeader::Format);
+    pub fn with_obs_fold_policy(mut self, policy: ObsFoldPolicy) -> Self {
+        self.obs_fold = policy;
+        self
     }
-    line.truncate(line.len() - 2);
-    let (method, uri, version) = parse_request_line(&line[..])?;
-    let mut fields = HeaderMap::new();
+
+    fn commit_pending_field(&mut self) -> Result<(), InvalidHeaderField> {
+        if let Some((name, value)) = self.field.take() {
+            let value = parse_header_field_value(&value)?;
+            self.fields.insert(name, value);
+        }
+        Ok(())
+    }
+
+    pub fn feed(&mut self, buf: &[u8]) -> Result<Parse, InvalidRequestHeader> {
+        let prev_len = self.pending.len();
+        self.pending.extend_from_slice(buf);
+
+        // `pos` only ever advances over lines we've fully processed (request
+        // line, committed fields, obs-fold continuations); we drain
+        // `pending` up to it before returning, on every path, so the next
+        // call to `feed` never re-scans lines this call already consumed.
+        let mut pos = 0;
+        let result = loop {
+            let nl = match self.pending[pos..].iter().position(|&b| b == b'\n') {
+                Some(i) => pos + i,
+                None => {
+                    if self.pending.len() >= LINE_CAP {
+                        return Err(InvalidRequestHeader::Malformed);
+                    }
+                    break Ok(Parse::Partial);
+                }
+            };
+            if nl == pos || self.pending[nl - 1] != b'\r' {
+                return Err(InvalidRequestHeader::Malformed);
+            }
+            let line_start = pos;
+            let line_end = nl - 1;
+            pos = nl + 1;
+
+            // rfc7230 section 3.2.4 (obs-fold): a line starting with SP or
+            // HTAB continues the previous field's value.
+            let is_continuation = line_start < line_end
+                && (self.pending[line_start] == b' '
+                    || self.pending[line_start] == b'\t');
+            if is_continuation {
+                if self.obs_fold == ObsFoldPolicy::Reject || self.field.is_none() {
+                    return Err(InvalidHeaderField::ObsFold.into());
+                }
+                let folded = trim_ows(&self.pending[line_start..line_end]).to_vec();
+                let (_, value) = self.field.as_mut().unwrap();
+                value.push(b' ');
+                value.extend_from_slice(&folded);
+                continue;
+            }
+            self.commit_pending_field()?;
+
+            let line = self.pending[line_start..line_end].to_vec();
+
+            if self.request_line.is_none() {
+                self.request_line = Some(parse_request_line(&line)?);
+                continue;
+            }
+            if line == b"" {
+                let (method, uri, version) = self.request_line.take().unwrap();
+                let header = Box::new(RequestHeader {
+                    method,
+                    uri,
+                    version,
+                    fields: std::mem::replace(&mut self.fields, HeaderMap::new()),
+                });
+                let bytes_consumed = pos.saturating_sub(prev_len);
+                break Ok(Parse::Complete { header, bytes_consumed });
+            }
+            self.field = Some(parse_header_field_name(&line)?);
+        };
+        self.pending.drain(..pos);
+        result
+    }
+}
+
+pub fn parse_request_header<B: BufRead>(stream: B)
+    -> Result<RequestHeader, InvalidRequestHeader>
+{
+    parse_request_header_with_obs_fold_policy(stream, ObsFoldPolicy::default())
+}
+
+pub fn parse_request_header_with_obs_fold_policy<B: BufRead>(
+    mut stream: B,
+    obs_fold: ObsFoldPolicy,
+) -> Result<RequestHeader, InvalidRequestHeader> {
+    let mut parser = RequestHeadParser::new().with_obs_fold_policy(obs_fold);
     loop {
-        next_line(&mut stream, &mut line)?;
-        if !line.ends_with(b"\r\n") {
-            return Err(InvalidRequestHeader::Format);
+        let buf = stream.fill_buf()?;
+        if buf.is_empty() {
+            return Err(InvalidRequestHeader::Truncated);
+        }
+        let parse = parser.feed(buf)?;
+        match parse {
+            Parse::Complete { header, bytes_consumed } => {
+                stream.consume(bytes_consumed);
+                return Ok(*header);
+            }
+            Parse::Partial => {
+                let len = buf.len();
+                stream.consume(len);
+            }
         }
-        line.truncate(line.len() - 2);
-        if line == b"" {
-            return Ok(RequestHeader { method, uri, version, fields });
+    }
+}
+
+fn write_version<W: Write>(mut stream: W, version: Version) -> io::Result<()> {
+    // TODO: Is this the way you're supposed to format bytes?
+    stream.write_all(
+        match version {
+            Version::HTTP_10 => b"HTTP/1.0",
+            // Anything we can't serialize (HTTP/0.9, HTTP/2, an unsupported
+            // HTTP/1.x we nonetheless parsed) falls back to HTTP/1.1 so the
+            // caller can always emit a valid status/request line, mirroring
+            // how `parse_request_line` treats any HTTP/1.x as HTTP/1.1.
+            _ => b"HTTP/1.1",
         }
-        let (name, value) = parse_header_field(&line)?;
-        fields.insert(name, value); // TODO: we should care about result, right?
+    )
+}
+
+fn write_header_fields<W: Write>(fields: &HeaderMap, mut stream: W)
+    -> io::Result<()>
+{
+    for (name, value) in fields.iter() {
+        stream.write_all(name.as_str().as_bytes())?;
+        stream.write_all(b": ")?;
+        stream.write_all(value.as_bytes())?;
+        stream.write_all(b"\r\n")?;
     }
+    stream.write_all(b"\r\n")
 }
 
 pub fn write_response_header<W: Write>(header: &ResponseHeader, stream: W)
@@ -105,14 +283,7 @@ pub fn write_response_header<W: Write>(header: &ResponseHeader, stream: W)
 {
     let mut stream = BufWriter::new(stream);
 
-    // TODO: Is this the way you're supposed to format bytes?
-    stream.write_all(
-        match header.version {
-            Version::HTTP_10 => b"HTTP/1.0",
-            Version::HTTP_11 => b"HTTP/1.1",
-            _ => panic!("Unsupported version"), // FIXME: Err? Or really panic?
-        }
-    )?;
+    write_version(&mut stream, header.version)?;
     stream.write_all(b" ")?;
     stream.write_all(header.status_code.as_str().as_bytes())?;
     stream.write_all(b" ")?;
@@ -123,8 +294,54 @@ pub fn write_response_header<W: Write>(header: &ResponseHeader, stream: W)
         .unwrap_or("Unknown Reason")
         .as_bytes()
     )?;
-    // TODO: Write header fields.
-    Ok(())
+    stream.write_all(b"\r\n")?;
+    write_header_fields(&header.fields, &mut stream)
+}
+
+fn write_request_target<W: Write>(mut stream: W, uri: &Uri) -> io::Result<()> {
+    if uri.scheme_part().is_some() {
+        // absolute-form, as used when talking to a proxy
+        write!(stream, "{}", uri)
+    } else if uri.path() == "*" {
+        // asterisk-form, e.g. "OPTIONS * HTTP/1.1"
+        stream.write_all(b"*")
+    } else {
+        // origin-form
+        stream.write_all(uri.path().as_bytes())?;
+        if let Some(query) = uri.query() {
+            stream.write_all(b"?")?;
+            stream.write_all(query.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_request_header<W: Write>(header: &RequestHeader, stream: W)
+    -> io::Result<()>
+{
+    let mut stream = BufWriter::new(stream);
+
+    stream.write_all(header.method.as_str().as_bytes())?;
+    stream.write_all(b" ")?;
+    write_request_target(&mut stream, &header.uri)?;
+    stream.write_all(b" ")?;
+    write_version(&mut stream, header.version)?;
+    stream.write_all(b"\r\n")?;
+
+    // RFC 7230 section 5.4: a client MUST send a Host header field in all
+    // HTTP/1.1 request messages.
+    if header.fields.contains_key(HOST) {
+        write_header_fields(&header.fields, &mut stream)
+    } else {
+        let mut fields = header.fields.clone();
+        if let Some(authority) = header.uri.authority_part() {
+            fields.insert(
+                HOST,
+                HeaderValue::from_str(authority.as_str()).unwrap(),
+            );
+        }
+        write_header_fields(&fields, &mut stream)
+    }
 }
 
 #[derive(Debug)]
@@ -132,7 +349,14 @@ pub enum InvalidRequestLine {
     Format,
     Method(InvalidMethod),
     Uri(InvalidUriBytes),
-    Version,
+    // The HTTP-version token itself isn't well-formed, e.g. "HTTP/1.1.1"
+    // or "FOO/1.1".
+    MalformedVersion,
+    // The HTTP-version token is well-formed but names a major/minor pair
+    // we don't implement (HTTP/0.9, HTTP/2, ...). Unlike `MalformedVersion`,
+    // a caller can use this to reply with 505 HTTP Version Not Supported
+    // instead of just closing the connection.
+    UnsupportedVersion { major: u8, minor: u8 },
 }
 
 impl From<InvalidMethod> for InvalidRequestLine {
@@ -151,34 +375,44 @@ pub fn parse_request_line(s: &[u8])
     -> Result<(Method, Uri, Version), InvalidRequestLine>
 {
     lazy_static! {
+        // method SP request-target SP HTTP-version
         static ref R: Regex = Regex::new(
-            // method SP request-target SP HTTP-version
             r"(?-u)^(\S+) (\S+) (\S+)$"
         ).unwrap();
+        // HTTP-version = HTTP-name "/" DIGIT "." DIGIT
+        static ref VERSION: Regex = Regex::new(
+            r"(?-u)^HTTP/([0-9])\.([0-9])$"
+        ).unwrap();
     }
     let cap = R.captures(s).ok_or(InvalidRequestLine::Format)?;
-    Ok((
-        Method::from_bytes(&cap[1])?,
-        Uri::from_shared(cap[2].into())?,
-        match &cap[3] {
-            // rfc 7230 section A: "Any server that implements name-based
-            // virtual hosts ought to disable support for HTTP/0.9."
-            b"HTTP/1.0" => Version::HTTP_10,
-            b"HTTP/1.1" => Version::HTTP_11,
-            // We don't support HTTP 0.9 or 2.0. 2.0 support may be added later.
-            // FIXME: Can we respond to an invalid version with 505 HTTP
-            // Version Not Supported? If not, unsupported major versions need a
-            // different error than invalid versions.
-            // FIXME: We should probably accept requests with version 1.2 and
-            // higher. Check the spec.
-            _ => return Err(InvalidRequestLine::Version),
-        },
-    ))
+    let method = Method::from_bytes(&cap[1])?;
+    let uri = Uri::from_shared(cap[2].into())?;
+
+    let version_cap = VERSION.captures(&cap[3])
+        .ok_or(InvalidRequestLine::MalformedVersion)?;
+    let major = version_cap[1][0] - b'0';
+    let minor = version_cap[2][0] - b'0';
+    let version = match (major, minor) {
+        // rfc 7230 section A: "Any server that implements name-based
+        // virtual hosts ought to disable support for HTTP/0.9."
+        (1, 0) => Version::HTTP_10,
+        // rfc 7230 section 2.6: a server should accept any minor version
+        // of HTTP/1.x as if it were HTTP/1.1.
+        (1, minor) if minor >= 1 => Version::HTTP_11,
+        (major, minor) => {
+            return Err(InvalidRequestLine::UnsupportedVersion { major, minor });
+        }
+    };
+
+    Ok((method, uri, version))
 }
 
 #[derive(Debug)]
 pub enum InvalidHeaderField {
     Format,
+    // obs-fold was used and the parser was configured to reject it (see
+    // `ObsFoldPolicy::Reject`).
+    ObsFold,
     Name(InvalidHeaderName),
     Value(InvalidHeaderValue),
 }
@@ -195,38 +429,62 @@ impl From<InvalidHeaderValue> for InvalidHeaderField {
     }
 }
 
-pub fn parse_header_field(s: &[u8])
-    -> Result<(HeaderName, HeaderValue), InvalidHeaderField>
+// rfc7230 section 3.2.4: Server MUST return 400 if there's whitespace
+// between field name and colon.
+fn parse_header_field_name(s: &[u8])
+    -> Result<(HeaderName, Vec<u8>), InvalidHeaderField>
 {
-    // TODO: support obs-fold e.g. within message/http
-    // (see rfc7230 section 3.2.4)
-
-    // rfc7230 section 3.2.4: Server MUST return 400 if there's whitespace
-    // between field name and colon.
-    // rfc7230 section 3.2.4: If obs-fold is used outside a message/http body,
-    // server MUST either return 400 or replace each such obs-fold with one or
-    // more SP chars.
     lazy_static! {
-        static ref R: Regex = Regex::new(concat!(
-            // token ":" OWS *field-content OWS
-            r"(?-u)^([!#$%&'*+.^_`|~0-9A-Za-z-]+):",
-            r"[\t ]*([!-~\x80-\xFF]([\t !-~\x80-\xFF]*[!-~\x80-\xFF])?)[\t ]*$",
+        // token ":"
+        static ref NAME: Regex = Regex::new(&(String::new()
+            + r"(?-u)^(" + TOKEN + r"):"
         )).unwrap();
     }
-    let cap = R.captures(s).ok_or(InvalidHeaderField::Format)?;
-    Ok((
-        HeaderName::from_bytes(&cap[1])?,
-        HeaderValue::from_bytes(&cap[2])?,
-    ))
+    let cap = NAME.captures(s).ok_or(InvalidHeaderField::Format)?;
+    let name = HeaderName::from_bytes(&cap[1])?;
+    let value = s[cap.get(0).unwrap().end()..].to_vec();
+    Ok((name, value))
+}
+
+// Validates OWS *field-content OWS. Used both for a field's own line and,
+// after obs-fold continuation lines have been joined in with a single SP
+// each (see `RequestHeadParser`), for the combined value.
+pub fn parse_header_field_value(s: &[u8])
+    -> Result<HeaderValue, InvalidHeaderField>
+{
+    lazy_static! {
+        static ref VALUE: Regex = Regex::new(
+            r"(?-u)^[\t ]*([!-~\x80-\xFF]([\t !-~\x80-\xFF]*[!-~\x80-\xFF])?)[\t ]*$"
+        ).unwrap();
+    }
+    let cap = VALUE.captures(s).ok_or(InvalidHeaderField::Format)?;
+    Ok(HeaderValue::from_bytes(&cap[1])?)
+}
+
+pub fn parse_header_field(s: &[u8])
+    -> Result<(HeaderName, HeaderValue), InvalidHeaderField>
+{
+    let (name, value) = parse_header_field_name(s)?;
+    let value = parse_header_field_value(&value)?;
+    Ok((name, value))
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         parse_request_header,
+        parse_request_header_with_obs_fold_policy,
         parse_request_line,
         parse_header_field,
+        InvalidHeaderField,
+        InvalidRequestHeader,
+        InvalidRequestLine,
+        ObsFoldPolicy,
+        Parse,
+        RequestHeader,
+        RequestHeadParser,
         ResponseHeader,
+        write_request_header,
         write_response_header,
     };
     use http::header::{
@@ -262,16 +520,111 @@ mod test {
         assert_eq!(h.fields["content-type"], "application/json");
     }
 
+    #[test]
+    fn test_request_head_parser_partial_then_complete() {
+        let mut parser = RequestHeadParser::new();
+
+        assert!(matches!(
+            parser.feed(b"GET / HTTP/1.1\r\nHost: foo").unwrap(),
+            Parse::Partial,
+        ));
+
+        match parser.feed(b".example.com\r\n\r\nbody-starts-here").unwrap() {
+            Parse::Complete { header, bytes_consumed } => {
+                assert_eq!(header.method, Method::GET);
+                assert_eq!(header.fields["host"], "foo.example.com");
+                assert_eq!(bytes_consumed, b".example.com\r\n\r\n".len());
+            }
+            Parse::Partial => panic!("expected Parse::Complete"),
+        }
+    }
+
+    #[test]
+    fn test_parse_request_header_unfolds_obs_fold_by_default() {
+        let mut s = Vec::new();
+        s.extend(&b"GET / HTTP/1.1\r\n"[..]);
+        s.extend(&b"X-Long: line one\r\n"[..]);
+        s.extend(&b" \tline two\r\n"[..]);
+        s.extend(&b"\r\n"[..]);
+
+        let h = parse_request_header(&s[..]).unwrap();
+        assert_eq!(h.fields["x-long"], "line one line two");
+    }
+
+    #[test]
+    fn test_parse_request_header_can_reject_obs_fold() {
+        let mut s = Vec::new();
+        s.extend(&b"GET / HTTP/1.1\r\n"[..]);
+        s.extend(&b"X-Long: line one\r\n"[..]);
+        s.extend(&b" line two\r\n"[..]);
+        s.extend(&b"\r\n"[..]);
+
+        let err = parse_request_header_with_obs_fold_policy(
+            &s[..],
+            ObsFoldPolicy::Reject,
+        ).unwrap_err();
+        assert!(matches!(
+            err,
+            InvalidRequestHeader::HeaderField(InvalidHeaderField::ObsFold),
+        ));
+    }
+
     #[test]
     fn test_write_response_header() {
         let mut s = Vec::new();
+        let mut fields = HeaderMap::new();
+        fields.insert(http::header::CONTENT_LENGTH, HeaderValue::from_static("0"));
         let h = ResponseHeader {
             status_code: StatusCode::from_u16(404).unwrap(),
             version: Version::HTTP_11,
+            fields,
+        };
+        write_response_header(&h, &mut s).unwrap();
+        assert_eq!(
+            s,
+            &b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n"[..],
+        );
+    }
+
+    #[test]
+    fn test_write_response_header_falls_back_on_unsupported_version() {
+        let mut s = Vec::new();
+        let h = ResponseHeader {
+            status_code: StatusCode::from_u16(505).unwrap(),
+            version: Version::HTTP_2,
             fields: HeaderMap::new(),
         };
         write_response_header(&h, &mut s).unwrap();
-        assert_eq!(s, b"HTTP/1.1 404 Not Found");
+        assert_eq!(s, &b"HTTP/1.1 505 HTTP Version Not Supported\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn test_write_request_header() {
+        let mut s = Vec::new();
+        let h = RequestHeader {
+            method: Method::GET,
+            uri: "/foo?bar=baz".parse().unwrap(),
+            version: Version::HTTP_11,
+            fields: HeaderMap::new(),
+        };
+        write_request_header(&h, &mut s).unwrap();
+        assert_eq!(s, &b"GET /foo?bar=baz HTTP/1.1\r\n\r\n"[..]);
+    }
+
+    #[test]
+    fn test_write_request_header_adds_host_from_authority() {
+        let mut s = Vec::new();
+        let h = RequestHeader {
+            method: Method::GET,
+            uri: "http://foo.example.com/bar".parse().unwrap(),
+            version: Version::HTTP_11,
+            fields: HeaderMap::new(),
+        };
+        write_request_header(&h, &mut s).unwrap();
+        assert_eq!(
+            s,
+            &b"GET http://foo.example.com/bar HTTP/1.1\r\nhost: foo.example.com\r\n\r\n"[..],
+        );
     }
 
     #[test]
@@ -291,6 +644,35 @@ mod test {
         assert_eq!(u.path(), "/bar");
         assert_eq!(u.query().unwrap(), "qux=19&qux=xyz");
         assert_eq!(v, Version::HTTP_10);
+
+        // rfc 7230 section 2.6: any HTTP/1.x is treated as HTTP/1.1.
+        let s = b"GET / HTTP/1.7";
+        let (_, _, v) = parse_request_line(s).unwrap();
+        assert_eq!(v, Version::HTTP_11);
+    }
+
+    #[test]
+    fn test_parse_request_line_unsupported_version() {
+        let s = b"GET / HTTP/2.0";
+        assert!(matches!(
+            parse_request_line(s),
+            Err(InvalidRequestLine::UnsupportedVersion { major: 2, minor: 0 }),
+        ));
+
+        let s = b"GET / HTTP/0.9";
+        assert!(matches!(
+            parse_request_line(s),
+            Err(InvalidRequestLine::UnsupportedVersion { major: 0, minor: 9 }),
+        ));
+    }
+
+    #[test]
+    fn test_parse_request_line_malformed_version() {
+        let s = b"GET / HTTP/1.1.1";
+        assert!(matches!(
+            parse_request_line(s),
+            Err(InvalidRequestLine::MalformedVersion),
+        ));
     }
 
     #[test]
@@ -1,4 +1,4 @@
-use crate::{QUOTED_STRING_1G, TOKEN};
+use crate::{trim_ows, QUOTED_STRING_1G, TOKEN};
 use lazy_static::lazy_static;
 use regex::bytes::Regex;
 use std::collections::HashMap;
@@ -38,22 +38,9 @@ pub fn parse_media_type(mut s: &[u8]) -> Result<MediaType, InvalidMediaType> {
 
     while s.len() > 0 {
         let cap = R2.captures(s).ok_or(InvalidMediaType)?;
-        let quoted_value = &cap[2];
-        let mut value;
-        if quoted_value[0] == b'"' {
-            assert_eq!(quoted_value[quoted_value.len()-1], b'"');
-            value = Vec::new();
-            for &c in &quoted_value[1..=quoted_value.len()-2] {
-                if c != b'\\' {
-                    value.push(c);
-                }
-            }
-        } else {
-            value = cap[2].to_vec();
-        }
         m.parameters.insert(
             String::from_utf8(cap[1].to_vec()).unwrap(),
-            value,
+            unquote_value(&cap[2]),
         );
         s = &s[cap.get(0).unwrap().end()..];
     }
@@ -61,10 +48,157 @@ pub fn parse_media_type(mut s: &[u8]) -> Result<MediaType, InvalidMediaType> {
     Ok(m)
 }
 
+// Strips the surrounding DQUOTEs (if any) and the backslash of each
+// quoted-pair from a token-or-quoted-string parameter value.
+fn unquote_value(quoted_value: &[u8]) -> Vec<u8> {
+    if quoted_value[0] != b'"' {
+        return quoted_value.to_vec();
+    }
+    assert_eq!(quoted_value[quoted_value.len()-1], b'"');
+    let mut value = Vec::new();
+    for &c in &quoted_value[1..=quoted_value.len()-2] {
+        if c != b'\\' {
+            value.push(c);
+        }
+    }
+    value
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaRange {
+    // "*" stands for the wildcard, as in "*/*" or "text/*".
+    pub type_: String,
+    pub subtype: String,
+    pub parameters: HashMap<String, Vec<u8>>,
+    // The "q" parameter, in [0, 1] with three-decimal precision; 1.0 if
+    // absent or malformed.
+    pub q: f64,
+}
+
+// Splits `s` on top-level commas, i.e. commas that aren't inside a
+// quoted-string parameter value.
+fn split_top_level_commas(s: &[u8]) -> Vec<&[u8]> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'"' => in_quotes = !in_quotes,
+            b'\\' if in_quotes => i += 1,
+            b',' if !in_quotes => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+pub fn parse_media_ranges(s: &[u8]) -> Result<Vec<MediaRange>, InvalidMediaType> {
+    lazy_static! {
+        // type "/" subtype *( OWS ";" OWS parameter ), but either half of
+        // the type may be "*".
+        static ref R1: Regex = Regex::new(&(String::new()
+            + r"(?-u)^(" + TOKEN + r"|\*)/(" + TOKEN + r"|\*)"
+        )).unwrap();
+
+        static ref R2: Regex = Regex::new(&(String::new()
+            + r"(?-u)^[\t ]*;[\t ]*(" + TOKEN + r")=("
+            + TOKEN + r"|" + QUOTED_STRING_1G + r")"
+        )).unwrap();
+    }
+
+    let mut ranges = Vec::new();
+    for item in split_top_level_commas(s) {
+        // RFC 7231 section 5.3.2 permits empty list elements between commas.
+        let mut item = trim_ows(item);
+        if item.is_empty() {
+            continue;
+        }
+
+        let cap = R1.captures(item).ok_or(InvalidMediaType)?;
+        let type_ = String::from_utf8(cap[1].to_vec()).unwrap();
+        let subtype = String::from_utf8(cap[2].to_vec()).unwrap();
+        item = &item[cap.get(0).unwrap().end()..];
+
+        let mut parameters = HashMap::new();
+        while !item.is_empty() {
+            let cap = R2.captures(item).ok_or(InvalidMediaType)?;
+            parameters.insert(
+                String::from_utf8(cap[1].to_vec()).unwrap(),
+                unquote_value(&cap[2]),
+            );
+            item = &item[cap.get(0).unwrap().end()..];
+        }
+
+        let q = parameters.get("q")
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|q| (q * 1000.0).round() / 1000.0)
+            .filter(|q| *q >= 0.0 && *q <= 1.0)
+            .unwrap_or(1.0);
+
+        ranges.push(MediaRange { type_, subtype, parameters, q });
+    }
+
+    Ok(ranges)
+}
+
+// How specifically `range` matches `candidate`, or `None` if it doesn't
+// match at all: higher is more specific, with ties broken by how many of
+// `range`'s parameters `candidate` also carries.
+fn specificity(range: &MediaRange, candidate: &MediaType) -> Option<u32> {
+    if range.type_ != "*" && range.type_ != candidate.type_ {
+        return None;
+    }
+    if range.subtype != "*" && range.subtype != candidate.subtype {
+        return None;
+    }
+
+    let level = match (range.type_ == "*", range.subtype == "*") {
+        (false, false) => 2, // exact type/subtype
+        (false, true) => 1,  // type/*
+        (true, _) => 0,       // */* (or the unusual */subtype)
+    };
+    let matching_params = range.parameters.iter()
+        .filter(|&(k, v)| candidate.parameters.get(k) == Some(v))
+        .count() as u32;
+
+    Some(level * 1000 + matching_params)
+}
+
+// Picks the entry of `available` that best satisfies `accept`, per RFC 7231
+// section 5.3.2: the highest client q-value wins, and a q-value of 0 means
+// "not acceptable". Ties are broken by specificity.
+pub fn negotiate<'a>(accept: &[MediaRange], available: &'a [MediaType])
+    -> Option<&'a MediaType>
+{
+    available.iter()
+        .filter_map(|candidate| {
+            accept.iter()
+                .filter_map(|range| {
+                    specificity(range, candidate).map(|spec| (range.q, spec))
+                })
+                // the most specific matching range decides this candidate's q
+                .max_by(|a, b| a.1.cmp(&b.1).then(a.0.partial_cmp(&b.0).unwrap()))
+                .map(|(q, spec)| (q, spec, candidate))
+        })
+        .filter(|&(q, _, _)| q > 0.0)
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)))
+        .map(|(_, _, candidate)| candidate)
+}
+
 #[cfg(test)]
 mod test {
     use crate::media_type::{
+        MediaRange,
         MediaType,
+        negotiate,
+        parse_media_ranges,
         parse_media_type,
     };
     use std::collections::HashMap;
@@ -106,4 +240,79 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn test_parse_media_ranges() {
+        let ranges = parse_media_ranges(
+            b"text/html;level=1, text/*;q=0.7, */*;q=0.3"
+        ).unwrap();
+        assert_eq!(ranges.len(), 3);
+
+        assert_eq!(ranges[0].type_, "text");
+        assert_eq!(ranges[0].subtype, "html");
+        assert_eq!(ranges[0].q, 1.0);
+
+        assert_eq!(ranges[1].type_, "text");
+        assert_eq!(ranges[1].subtype, "*");
+        assert_eq!(ranges[1].q, 0.7);
+
+        assert_eq!(ranges[2].type_, "*");
+        assert_eq!(ranges[2].subtype, "*");
+        assert_eq!(ranges[2].q, 0.3);
+    }
+
+    #[test]
+    fn test_negotiate_prefers_highest_q_then_specificity() {
+        let accept = parse_media_ranges(
+            b"text/html;q=0.9, text/*;q=0.9, application/json;q=0.3"
+        ).unwrap();
+        let available = vec![
+            MediaType {
+                type_: "application".to_string(),
+                subtype: "json".to_string(),
+                parameters: HashMap::new(),
+            },
+            MediaType {
+                type_: "text".to_string(),
+                subtype: "plain".to_string(),
+                parameters: HashMap::new(),
+            },
+            MediaType {
+                type_: "text".to_string(),
+                subtype: "html".to_string(),
+                parameters: HashMap::new(),
+            },
+        ];
+
+        let chosen = negotiate(&accept, &available).unwrap();
+        assert_eq!(chosen.subtype, "html");
+    }
+
+    #[test]
+    fn test_negotiate_excludes_q_zero() {
+        let accept = parse_media_ranges(b"text/html;q=0, */*").unwrap();
+        let available = vec![
+            MediaType {
+                type_: "text".to_string(),
+                subtype: "html".to_string(),
+                parameters: HashMap::new(),
+            },
+            MediaType {
+                type_: "application".to_string(),
+                subtype: "json".to_string(),
+                parameters: HashMap::new(),
+            },
+        ];
+
+        let chosen = negotiate(&accept, &available).unwrap();
+        assert_eq!(chosen.subtype, "json");
+    }
+
+    #[test]
+    fn test_media_range_clone_and_eq() {
+        let r: MediaRange = parse_media_ranges(b"text/plain").unwrap()
+            .into_iter().next().unwrap();
+        let r2 = r.clone();
+        assert_eq!(r, r2);
+    }
 }